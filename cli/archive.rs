@@ -0,0 +1,688 @@
+// Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
+
+//! Readers for the archive formats that modules can be imported from.
+
+use deno_core::error::custom_error;
+use deno_core::error::AnyError;
+use flate2::read::DeflateDecoder;
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+/// Compressed data is fed to `GunzipStream` in pieces of this size, so that
+/// it stops decompressing soon after its output exceeds its limit.
+const GUNZIP_PIECE_SIZE: usize = 1024;
+const TAR_BLOCK_SIZE: usize = 512;
+const ZIP_END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP_CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+const ZIP_LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+const ZIP_STORED: u16 = 0;
+const ZIP_DEFLATED: u16 = 8;
+
+/// Returns true if the path names a tarball, optionally gzip compressed.
+pub fn is_tarball(path: &str) -> bool {
+  path.ends_with(".tar") || path.ends_with(".tar.gz") || path.ends_with(".tgz")
+}
+
+/// Returns true if the path names a gzip compressed file that is not a
+/// tarball, e.g. `mod.ts.gz`.
+pub fn is_gzip_file(path: &str) -> bool {
+  path.ends_with(".gz") && !is_tarball(path)
+}
+
+/// Decompresses `bytes` if they are gzip compressed, and returns them as is
+/// otherwise, e.g. if a `Content-Encoding` was already decoded.
+/// Decompression stops once the output exceeds `limit` bytes, so callers
+/// can reject oversized data without inflating all of it.
+pub fn maybe_gunzip(bytes: Vec<u8>, limit: u64) -> Result<Vec<u8>, AnyError> {
+  if !bytes.starts_with(GZIP_MAGIC) {
+    return Ok(bytes);
+  }
+  let mut buf = Vec::new();
+  GzDecoder::new(bytes.as_slice())
+    .take(limit.saturating_add(1))
+    .read_to_end(&mut buf)
+    .map_err(invalid_gzip)?;
+  Ok(buf)
+}
+
+/// Decompresses data that arrives in chunks if it is gzip compressed, and
+/// passes it through as is otherwise, like `maybe_gunzip()`.
+pub struct GunzipStream {
+  state: GunzipState,
+  limit: u64,
+  output_len: u64,
+}
+
+enum GunzipState {
+  /// Too few bytes arrived yet to tell whether the data is compressed.
+  Undecided(Vec<u8>),
+  Compressed(flate2::write::GzDecoder<Vec<u8>>),
+  Plain,
+}
+
+impl GunzipStream {
+  /// Decompression stops once the output exceeds `limit` bytes, see
+  /// `output_len()`.
+  pub fn new(limit: u64) -> Self {
+    Self {
+      state: GunzipState::Undecided(Vec::new()),
+      limit,
+      output_len: 0,
+    }
+  }
+
+  /// The number of bytes returned so far. The data should be rejected if
+  /// this exceeds the limit, as the rest of it was not decompressed.
+  pub fn output_len(&self) -> u64 {
+    self.output_len
+  }
+
+  /// Feeds the next `chunk` of data, and returns the output that is ready.
+  pub fn write(&mut self, chunk: &[u8]) -> Result<Vec<u8>, AnyError> {
+    let output = match &mut self.state {
+      GunzipState::Undecided(head) => {
+        head.extend_from_slice(chunk);
+        if head.len() < GZIP_MAGIC.len() {
+          return Ok(Vec::new());
+        }
+        let head = std::mem::take(head);
+        if head.starts_with(GZIP_MAGIC) {
+          self.state =
+            GunzipState::Compressed(flate2::write::GzDecoder::new(Vec::new()));
+          return self.write(&head);
+        }
+        self.state = GunzipState::Plain;
+        head
+      }
+      GunzipState::Compressed(decoder) => {
+        for piece in chunk.chunks(GUNZIP_PIECE_SIZE) {
+          if self.output_len + decoder.get_ref().len() as u64 > self.limit {
+            break;
+          }
+          decoder.write_all(piece).map_err(invalid_gzip)?;
+        }
+        std::mem::take(decoder.get_mut())
+      }
+      GunzipState::Plain => chunk.to_vec(),
+    };
+    self.output_len += output.len() as u64;
+    Ok(output)
+  }
+
+  /// Ends the data, and returns the rest of the output. Fails if compressed
+  /// data is incomplete.
+  pub fn finish(&mut self) -> Result<Vec<u8>, AnyError> {
+    let state = std::mem::replace(&mut self.state, GunzipState::Plain);
+    let output = match state {
+      GunzipState::Undecided(head) => head,
+      GunzipState::Compressed(_) if self.output_len > self.limit => Vec::new(),
+      GunzipState::Compressed(decoder) => {
+        decoder.finish().map_err(invalid_gzip)?
+      }
+      GunzipState::Plain => Vec::new(),
+    };
+    self.output_len += output.len() as u64;
+    Ok(output)
+  }
+}
+
+fn invalid_gzip(err: io::Error) -> AnyError {
+  custom_error("InvalidData", format!("Invalid gzip data: {}", err))
+}
+
+/// Reads the regular file at `path` from an uncompressed tarball. Returns
+/// `None` if there is no such file. Names longer than the 100 bytes of the
+/// header are read from ustar prefixes, GNU long name entries and pax
+/// extended headers.
+pub fn read_tar_entry(
+  archive: &[u8],
+  path: &str,
+) -> Result<Option<Vec<u8>>, AnyError> {
+  let path = normalize_entry_name(path);
+  // Names given by extended headers for the next entry, and by pax global
+  // headers for all following entries.
+  let mut maybe_long_name: Option<String> = None;
+  let mut maybe_global_name: Option<String> = None;
+
+  let mut offset = 0;
+  while offset + TAR_BLOCK_SIZE <= archive.len() {
+    let header = &archive[offset..offset + TAR_BLOCK_SIZE];
+    // The archive ends with two zero blocks.
+    if header.iter().all(|b| *b == 0) {
+      break;
+    }
+    let size = parse_octal(&header[124..136])?;
+    let data_start = offset + TAR_BLOCK_SIZE;
+    let data_end = data_start + size;
+    if data_end > archive.len() {
+      return Err(invalid_tarball("entry exceeds the archive size"));
+    }
+    let data = &archive[data_start..data_end];
+
+    // Only regular files can be imported; directories and links are
+    // skipped.
+    match header[156] {
+      b'L' => maybe_long_name = Some(read_str(data)),
+      b'x' => {
+        if let Some(name) = parse_pax_path(data)? {
+          maybe_long_name = Some(name);
+        }
+      }
+      b'g' => {
+        if let Some(name) = parse_pax_path(data)? {
+          maybe_global_name = Some(name);
+        }
+      }
+      type_flag => {
+        let name = match maybe_long_name.take() {
+          Some(name) => name,
+          None => match &maybe_global_name {
+            Some(name) => name.clone(),
+            None => read_header_name(header),
+          },
+        };
+        if (type_flag == b'0' || type_flag == 0)
+          && normalize_entry_name(&name) == path
+        {
+          return Ok(Some(data.to_vec()));
+        }
+      }
+    }
+
+    let padded_size = (size + TAR_BLOCK_SIZE - 1) / TAR_BLOCK_SIZE;
+    offset = data_start + padded_size * TAR_BLOCK_SIZE;
+  }
+
+  Ok(None)
+}
+
+/// Reads the name of an entry from its header. The ustar format splits
+/// long names into a prefix and a name.
+fn read_header_name(header: &[u8]) -> String {
+  let name = read_str(&header[0..100]);
+  if &header[257..262] != b"ustar" {
+    return name;
+  }
+  let prefix = read_str(&header[345..500]);
+  if prefix.is_empty() {
+    name
+  } else {
+    format!("{}/{}", prefix, name)
+  }
+}
+
+/// Returns the `path` of a pax extended header, whose records have the form
+/// `<length> <key>=<value>\n`, where the length includes the whole record.
+fn parse_pax_path(data: &[u8]) -> Result<Option<String>, AnyError> {
+  let mut maybe_path = None;
+  let mut rest = data;
+  while !rest.is_empty() && rest[0] != 0 {
+    let space = rest
+      .iter()
+      .position(|b| *b == b' ')
+      .ok_or_else(|| invalid_tarball("invalid pax header"))?;
+    let len = std::str::from_utf8(&rest[..space])
+      .ok()
+      .and_then(|len| len.parse::<usize>().ok())
+      .filter(|len| *len > space + 1 && *len <= rest.len())
+      .ok_or_else(|| invalid_tarball("invalid pax header"))?;
+    // The record ends with a newline.
+    let record = &rest[space + 1..len - 1];
+    if record.starts_with(b"path=") {
+      maybe_path = Some(String::from_utf8_lossy(&record[5..]).into_owned());
+    }
+    rest = &rest[len..];
+  }
+  Ok(maybe_path)
+}
+
+/// A zip archive held in memory, e.g. one appended to an executable. Only
+/// stored and deflated entries are supported, and Zip64 is not.
+pub struct ZipArchive {
+  bytes: Vec<u8>,
+  entries: HashMap<String, ZipEntry>,
+}
+
+struct ZipEntry {
+  method: u16,
+  compressed_size: usize,
+  uncompressed_size: usize,
+  local_header_offset: usize,
+}
+
+impl ZipArchive {
+  /// Reads the central directory of the archive at the end of `bytes`. Any
+  /// data before the archive, such as an executable, is ignored.
+  pub fn new(bytes: Vec<u8>) -> Result<Self, AnyError> {
+    // The end of central directory record is at least 22 bytes long, and
+    // is followed by a comment of at most 64 KiB.
+    let min_start = bytes.len().saturating_sub(22 + u16::MAX as usize);
+    let end = (min_start..=bytes.len().saturating_sub(22))
+      .rev()
+      .find(|i| read_u32(&bytes, *i) == Some(ZIP_END_OF_CENTRAL_DIRECTORY))
+      .ok_or_else(|| invalid_zip("end of central directory not found"))?;
+    let entry_count = read_u16(&bytes, end + 10).unwrap() as usize;
+    let directory_size = read_u32(&bytes, end + 12).unwrap() as usize;
+    let directory_offset = read_u32(&bytes, end + 16).unwrap() as usize;
+    // Offsets are relative to the start of the archive, which is not the
+    // start of `bytes` if data was prepended to it.
+    let base = end
+      .checked_sub(directory_size + directory_offset)
+      .ok_or_else(|| invalid_zip("invalid central directory size"))?;
+
+    let mut entries = HashMap::new();
+    let mut offset = base + directory_offset;
+    for _ in 0..entry_count {
+      if read_u32(&bytes, offset) != Some(ZIP_CENTRAL_DIRECTORY_HEADER) {
+        return Err(invalid_zip("invalid central directory entry"));
+      }
+      let header = bytes
+        .get(offset..offset + 46)
+        .ok_or_else(|| invalid_zip("truncated central directory"))?;
+      let method = read_u16(header, 10).unwrap();
+      let compressed_size = read_u32(header, 20).unwrap();
+      let uncompressed_size = read_u32(header, 24).unwrap();
+      let name_len = read_u16(header, 28).unwrap() as usize;
+      let extra_len = read_u16(header, 30).unwrap() as usize;
+      let comment_len = read_u16(header, 32).unwrap() as usize;
+      let local_header_offset = read_u32(header, 42).unwrap();
+      if compressed_size == u32::MAX
+        || uncompressed_size == u32::MAX
+        || local_header_offset == u32::MAX
+      {
+        return Err(invalid_zip("Zip64 archives are not supported"));
+      }
+      let name = bytes
+        .get(offset + 46..offset + 46 + name_len)
+        .ok_or_else(|| invalid_zip("truncated central directory"))?;
+      let name = String::from_utf8_lossy(name).into_owned();
+      // Directories have no contents.
+      if !name.ends_with('/') {
+        entries.insert(
+          normalize_entry_name(&name).to_string(),
+          ZipEntry {
+            method,
+            compressed_size: compressed_size as usize,
+            uncompressed_size: uncompressed_size as usize,
+            local_header_offset: base + local_header_offset as usize,
+          },
+        );
+      }
+      offset += 46 + name_len + extra_len + comment_len;
+    }
+
+    Ok(Self { bytes, entries })
+  }
+
+  /// Reads the file at `path` from the archive. Returns `None` if there is
+  /// no such file. Fails if its contents are larger or smaller than the
+  /// size recorded in the central directory.
+  pub fn read(&self, path: &str) -> Result<Option<Vec<u8>>, AnyError> {
+    let entry = match self.entries.get(normalize_entry_name(path)) {
+      Some(entry) => entry,
+      None => return Ok(None),
+    };
+    let offset = entry.local_header_offset;
+    if read_u32(&self.bytes, offset) != Some(ZIP_LOCAL_FILE_HEADER) {
+      return Err(invalid_zip("invalid local file header"));
+    }
+    // The local header repeats the name, with a possibly different extra
+    // field.
+    let name_len = read_u16(&self.bytes, offset + 26)
+      .ok_or_else(|| invalid_zip("truncated local file header"))?;
+    let extra_len = read_u16(&self.bytes, offset + 28)
+      .ok_or_else(|| invalid_zip("truncated local file header"))?;
+    let data_start = offset + 30 + name_len as usize + extra_len as usize;
+    let data = self
+      .bytes
+      .get(data_start..data_start + entry.compressed_size)
+      .ok_or_else(|| invalid_zip("entry exceeds the archive size"))?;
+    let contents = match entry.method {
+      ZIP_STORED => data.to_vec(),
+      ZIP_DEFLATED => {
+        // Reading one byte more than expected is enough to tell that the
+        // entry is larger, without inflating all of it.
+        let mut buf = Vec::new();
+        DeflateDecoder::new(data)
+          .take(entry.uncompressed_size as u64 + 1)
+          .read_to_end(&mut buf)
+          .map_err(|err| invalid_zip(&err.to_string()))?;
+        buf
+      }
+      method => {
+        return Err(invalid_zip(&format!(
+          "unsupported compression method {}",
+          method
+        )))
+      }
+    };
+    if contents.len() != entry.uncompressed_size {
+      return Err(invalid_zip(
+        "entry size doesn't match the central directory",
+      ));
+    }
+    Ok(Some(contents))
+  }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+  let bytes = bytes.get(offset..offset + 2)?;
+  Some(u16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+  let bytes = bytes.get(offset..offset + 4)?;
+  Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn normalize_entry_name(name: &str) -> &str {
+  name.trim_start_matches("./").trim_start_matches('/')
+}
+
+fn read_str(field: &[u8]) -> String {
+  let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+  String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn parse_octal(field: &[u8]) -> Result<usize, AnyError> {
+  let s = read_str(field);
+  let s = s.trim();
+  if s.is_empty() {
+    return Ok(0);
+  }
+  usize::from_str_radix(s, 8)
+    .map_err(|_| invalid_tarball(&format!("invalid entry size \"{}\"", s)))
+}
+
+fn invalid_tarball(reason: &str) -> AnyError {
+  custom_error("InvalidData", format!("Invalid tarball: {}", reason))
+}
+
+fn invalid_zip(reason: &str) -> AnyError {
+  custom_error("InvalidData", format!("Invalid zip archive: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn tar_header(name: &str, size: usize, type_flag: u8) -> Vec<u8> {
+    let mut header = vec![0; TAR_BLOCK_SIZE];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    let size = format!("{:011o}\0", size);
+    header[124..136].copy_from_slice(size.as_bytes());
+    header[156] = type_flag;
+    header
+  }
+
+  fn tarball(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let entries = entries
+      .iter()
+      .map(|(name, data)| (*name, b'0', *data))
+      .collect::<Vec<_>>();
+    tarball_with_types(&entries)
+  }
+
+  fn tarball_with_types(entries: &[(&str, u8, &[u8])]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    for (name, type_flag, data) in entries {
+      archive.extend(tar_header(name, data.len(), *type_flag));
+      archive.extend(data.iter());
+      let padding =
+        (TAR_BLOCK_SIZE - data.len() % TAR_BLOCK_SIZE) % TAR_BLOCK_SIZE;
+      archive.extend(vec![0; padding]);
+    }
+    archive.extend(vec![0; TAR_BLOCK_SIZE * 2]);
+    archive
+  }
+
+  /// Builds a zip archive of stored entries.
+  fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in entries {
+      let offset = archive.len() as u32;
+      archive.extend(&ZIP_LOCAL_FILE_HEADER.to_le_bytes());
+      archive.extend(&[0; 14]);
+      archive.extend(&(data.len() as u32).to_le_bytes());
+      archive.extend(&(data.len() as u32).to_le_bytes());
+      archive.extend(&(name.len() as u16).to_le_bytes());
+      archive.extend(&0u16.to_le_bytes());
+      archive.extend(name.as_bytes());
+      archive.extend(data.iter());
+
+      directory.extend(&ZIP_CENTRAL_DIRECTORY_HEADER.to_le_bytes());
+      directory.extend(&[0; 16]);
+      directory.extend(&(data.len() as u32).to_le_bytes());
+      directory.extend(&(data.len() as u32).to_le_bytes());
+      directory.extend(&(name.len() as u16).to_le_bytes());
+      directory.extend(&[0; 12]);
+      directory.extend(&offset.to_le_bytes());
+      directory.extend(name.as_bytes());
+    }
+    let directory_offset = archive.len() as u32;
+    archive.extend(&directory);
+    archive.extend(&ZIP_END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    archive.extend(&[0; 4]);
+    archive.extend(&(entries.len() as u16).to_le_bytes());
+    archive.extend(&(entries.len() as u16).to_le_bytes());
+    archive.extend(&(directory.len() as u32).to_le_bytes());
+    archive.extend(&directory_offset.to_le_bytes());
+    archive.extend(&0u16.to_le_bytes());
+    archive
+  }
+
+  #[test]
+  fn test_is_tarball() {
+    assert!(is_tarball("vendor.tar"));
+    assert!(is_tarball("vendor.tar.gz"));
+    assert!(is_tarball("vendor.tgz"));
+    assert!(!is_tarball("vendor.gz"));
+    assert!(!is_tarball("mod.ts"));
+  }
+
+  #[test]
+  fn test_is_gzip_file() {
+    assert!(is_gzip_file("mod.ts.gz"));
+    assert!(!is_gzip_file("vendor.tar.gz"));
+    assert!(!is_gzip_file("mod.ts"));
+  }
+
+  #[test]
+  fn test_maybe_gunzip() {
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    let mut encoder =
+      GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(b"export const a = 1;\n").unwrap();
+    let compressed = encoder.finish().unwrap();
+    assert_eq!(
+      maybe_gunzip(compressed.clone(), u64::MAX).unwrap(),
+      b"export const a = 1;\n"
+    );
+    // Decompression stops right after the limit.
+    assert_eq!(maybe_gunzip(compressed, 5).unwrap(), b"export");
+    // Data that isn't compressed is kept as is.
+    assert_eq!(
+      maybe_gunzip(b"export const a = 1;\n".to_vec(), u64::MAX).unwrap(),
+      b"export const a = 1;\n"
+    );
+    assert!(maybe_gunzip(b"\x1f\x8bgarbage".to_vec(), u64::MAX).is_err());
+  }
+
+  #[test]
+  fn test_gunzip_stream() {
+    use flate2::write::GzEncoder;
+    let source = (0..20_000)
+      .map(|i| format!("export const a{} = {};\n", i, i * 7919 % 10007))
+      .collect::<String>();
+    let mut encoder =
+      GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(source.as_bytes()).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let gunzip_chunks = |chunks: std::slice::Chunks<u8>, limit| {
+      let mut stream = GunzipStream::new(limit);
+      let mut output = Vec::new();
+      for chunk in chunks {
+        output.extend(stream.write(chunk)?);
+      }
+      output.extend(stream.finish()?);
+      Ok::<_, AnyError>(output)
+    };
+    for chunk_size in &[1, 7, compressed.len()] {
+      let output =
+        gunzip_chunks(compressed.chunks(*chunk_size), u64::MAX).unwrap();
+      assert_eq!(output, source.as_bytes());
+      // Data that isn't compressed is passed through.
+      let output =
+        gunzip_chunks(source.as_bytes().chunks(*chunk_size), u64::MAX).unwrap();
+      assert_eq!(output, source.as_bytes());
+    }
+
+    // Decompression stops soon after the limit.
+    let mut stream = GunzipStream::new(100);
+    let output = stream.write(&compressed).unwrap();
+    assert!(output.len() > 100 && output.len() < source.len());
+    assert_eq!(stream.output_len(), output.len() as u64);
+
+    // Incomplete data is an error.
+    let truncated = &compressed[..compressed.len() - 4];
+    assert!(gunzip_chunks(truncated.chunks(7), u64::MAX).is_err());
+  }
+
+  #[test]
+  fn test_read_tar_entry() {
+    let archive = tarball(&[
+      ("./mod.ts", &b"export * from './sub/util.js';\n"[..]),
+      ("sub/util.js", &b"export const a = 1;\n"[..]),
+    ]);
+    assert_eq!(
+      read_tar_entry(&archive, "mod.ts").unwrap().unwrap(),
+      b"export * from './sub/util.js';\n"
+    );
+    assert_eq!(
+      read_tar_entry(&archive, "/sub/util.js").unwrap().unwrap(),
+      b"export const a = 1;\n"
+    );
+    assert!(read_tar_entry(&archive, "missing.ts").unwrap().is_none());
+  }
+
+  #[test]
+  fn test_read_tar_entry_truncated() {
+    let mut archive = tarball(&[("mod.ts", &b"export const a = 1;\n"[..])]);
+    archive.truncate(TAR_BLOCK_SIZE + 4);
+    assert!(read_tar_entry(&archive, "mod.ts").is_err());
+  }
+
+  #[test]
+  fn test_read_tar_entry_long_names() {
+    let long_name = format!("{}/mod.ts", "a".repeat(120));
+    let pax_name = format!("{}/util.js", "b".repeat(120));
+    let pax_record = format!("path={}\n", pax_name);
+    // The length of a pax record includes the length field itself.
+    let pax_record = format!("{} {}", pax_record.len() + 4, pax_record);
+    let archive = tarball_with_types(&[
+      ("././@LongLink", b'L', long_name.as_bytes()),
+      ("truncated", b'0', &b"export * from './util.js';\n"[..]),
+      ("PaxHeaders/util.js", b'x', pax_record.as_bytes()),
+      ("truncated", b'0', &b"export const a = 1;\n"[..]),
+    ]);
+    assert_eq!(
+      read_tar_entry(&archive, &long_name).unwrap().unwrap(),
+      b"export * from './util.js';\n"
+    );
+    assert_eq!(
+      read_tar_entry(&archive, &pax_name).unwrap().unwrap(),
+      b"export const a = 1;\n"
+    );
+    // The extended headers only name the entry that follows them.
+    assert!(read_tar_entry(&archive, "truncated").unwrap().is_none());
+  }
+
+  #[test]
+  fn test_zip_archive() {
+    let archive = zip(&[
+      ("mod.ts", &b"export * from './sub/util.js';\n"[..]),
+      ("sub/", &b""[..]),
+      ("sub/util.js", &b"export const a = 1;\n"[..]),
+    ]);
+    // Data prepended to the archive, e.g. an executable, is skipped.
+    let mut bytes = b"#!/usr/bin/env executable\n".to_vec();
+    bytes.extend(archive);
+    let archive = ZipArchive::new(bytes).unwrap();
+    assert_eq!(
+      archive.read("./mod.ts").unwrap().unwrap(),
+      b"export * from './sub/util.js';\n"
+    );
+    assert_eq!(
+      archive.read("/sub/util.js").unwrap().unwrap(),
+      b"export const a = 1;\n"
+    );
+    assert!(archive.read("sub/").unwrap().is_none());
+    assert!(archive.read("missing.ts").unwrap().is_none());
+  }
+
+  #[test]
+  fn test_zip_archive_deflated() {
+    let bytes =
+      std::fs::read(test_util::root_path().join("cli/tests/zip/embedded.zip"))
+        .unwrap();
+    let archive = ZipArchive::new(bytes).unwrap();
+    assert_eq!(
+      archive.read("mod.ts").unwrap().unwrap(),
+      b"export { a } from \"./sub/util.js\";\n"
+    );
+  }
+
+  #[test]
+  fn test_zip_archive_deflated_size() {
+    let mut encoder =
+      flate2::write::DeflateEncoder::new(Vec::new(), Default::default());
+    encoder.write_all(&[b'a'; 4096]).unwrap();
+    let data = encoder.finish().unwrap();
+    let mut archive = zip(&[("mod.ts", &data[..])]);
+    let len = archive.len();
+    let directory = read_u32(&archive, len - 6).unwrap() as usize;
+    archive[8..10].copy_from_slice(&ZIP_DEFLATED.to_le_bytes());
+    archive[directory + 10..directory + 12]
+      .copy_from_slice(&ZIP_DEFLATED.to_le_bytes());
+    // The central directory records the compressed size as the size of the
+    // entry, which inflates to more than that.
+    let err = ZipArchive::new(archive.clone())
+      .unwrap()
+      .read("mod.ts")
+      .unwrap_err();
+    assert!(err.to_string().starts_with("Invalid zip archive"));
+
+    archive[directory + 24..directory + 28]
+      .copy_from_slice(&4096u32.to_le_bytes());
+    assert_eq!(
+      ZipArchive::new(archive.clone())
+        .unwrap()
+        .read("mod.ts")
+        .unwrap()
+        .unwrap(),
+      vec![b'a'; 4096]
+    );
+
+    archive[directory + 24..directory + 28]
+      .copy_from_slice(&8192u32.to_le_bytes());
+    assert!(ZipArchive::new(archive).unwrap().read("mod.ts").is_err());
+  }
+
+  #[test]
+  fn test_zip_archive_invalid() {
+    assert!(ZipArchive::new(b"not a zip archive".to_vec()).is_err());
+    let mut archive = zip(&[("mod.ts", &b"export const a = 1;\n"[..])]);
+    // Claim a central directory larger than the archive.
+    let len = archive.len();
+    archive[len - 10..len - 6].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(ZipArchive::new(archive).is_err());
+  }
+}
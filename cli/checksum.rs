@@ -1,15 +1,34 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
 pub fn gen(v: &[&[u8]]) -> String {
-  let mut ctx = ring::digest::Context::new(&ring::digest::SHA256);
+  let mut hasher = Hasher::default();
   for src in v {
-    ctx.update(src);
-  }
-  let digest = ctx.finish();
-  let out: Vec<String> = digest
-    .as_ref()
-    .iter()
-    .map(|byte| format!("{:02x}", byte))
-    .collect();
-  out.join("")
+    hasher.update(src);
+  }
+  hasher.finish()
+}
+
+/// Computes the same checksum as `gen()` over data that arrives in chunks.
+pub struct Hasher(ring::digest::Context);
+
+impl Default for Hasher {
+  fn default() -> Self {
+    Hasher(ring::digest::Context::new(&ring::digest::SHA256))
+  }
+}
+
+impl Hasher {
+  pub fn update(&mut self, data: &[u8]) {
+    self.0.update(data);
+  }
+
+  pub fn finish(self) -> String {
+    let digest = self.0.finish();
+    let out: Vec<String> = digest
+      .as_ref()
+      .iter()
+      .map(|byte| format!("{:02x}", byte))
+      .collect();
+    out.join("")
+  }
 }
@@ -10,6 +10,7 @@
 //!   exceptions.
 
 use crate::ast::DiagnosticBuffer;
+use crate::http_util::HttpStatusError;
 use crate::import_map::ImportMapError;
 use deno_core::error::AnyError;
 use deno_core::serde_json;
@@ -146,6 +147,10 @@ fn get_serde_json_error_class(
   }
 }
 
+fn get_http_status_error_class(_: &HttpStatusError) -> &'static str {
+  "Error"
+}
+
 fn get_diagnostic_class(_: &DiagnosticBuffer) -> &'static str {
   "SyntaxError"
 }
@@ -208,6 +213,10 @@ pub(crate) fn get_error_class_name(e: &AnyError) -> &'static str {
       e.downcast_ref::<serde_json::error::Error>()
         .map(get_serde_json_error_class)
     })
+    .or_else(|| {
+      e.downcast_ref::<HttpStatusError>()
+        .map(get_http_status_error_class)
+    })
     .or_else(|| {
       e.downcast_ref::<DiagnosticBuffer>()
         .map(get_diagnostic_class)
@@ -1,5 +1,6 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
+use crate::colors;
 use crate::deno_dir;
 use crate::file_fetcher::SourceFileFetcher;
 use crate::flags;
@@ -179,6 +180,9 @@ impl GlobalState {
       module_graph_loader
         .add_to_graph(&module_specifier, maybe_referrer)
         .await?;
+      for (_, warning) in self.file_fetcher.take_warnings() {
+        eprintln!("{} {}", colors::yellow("Warning"), warning);
+      }
       let module_graph = module_graph_loader.get_graph();
 
       let out = self
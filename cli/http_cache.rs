@@ -4,6 +4,7 @@
 /// as defined in RFC 7234 (https://tools.ietf.org/html/rfc7234).
 /// Currently it's a very simplified version to fulfill Deno needs
 /// at hand.
+use crate::checksum;
 use crate::fs as deno_fs;
 use crate::http_util::HeadersMap;
 use deno_core::error::AnyError;
@@ -11,11 +12,53 @@ use deno_core::serde_json;
 use deno_core::url::Url;
 use serde::Deserialize;
 use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fs;
 use std::fs::File;
 use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+use tokio::io::AsyncWriteExt;
+use walkdir::WalkDir;
+
+/// How many times `HttpCache::get()` reads an entry whose content doesn't
+/// match its metadata, e.g. because it is being replaced, before giving up.
+const GET_ATTEMPTS: usize = 5;
+
+/// Returns the name of a temporary file next to `filename`, unique across
+/// processes and threads, to be renamed to `filename` once it is complete.
+fn temp_filename(filename: &Path) -> PathBuf {
+  static NEXT_TEMP_ID: AtomicUsize = AtomicUsize::new(0);
+  let mut temp_filename = filename.to_owned().into_os_string();
+  temp_filename.push(format!(
+    ".{}-{}.tmp",
+    std::process::id(),
+    NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)
+  ));
+  PathBuf::from(temp_filename)
+}
+
+/// Writes `data` to a temporary file next to `filename`, which is then
+/// renamed to `filename`, so that concurrent readers, including other
+/// processes, never see a partially written file.
+fn write_file_atomically(filename: &Path, data: &[u8]) -> io::Result<()> {
+  let temp_filename = temp_filename(filename);
+  let result = deno_fs::write_file(&temp_filename, data, 0o666)
+    .and_then(|_| fs::rename(&temp_filename, filename));
+  if result.is_err() {
+    let _ = fs::remove_file(&temp_filename);
+  }
+  result
+}
 
 /// Turn base of url (scheme, hostname, port) into a valid filename.
 /// This method replaces port part with a special string token (because
@@ -73,19 +116,28 @@ pub fn url_to_filename(url: &Url) -> PathBuf {
 #[derive(Clone)]
 pub struct HttpCache {
   pub location: PathBuf,
+  query_ignored_hosts: Arc<HashSet<String>>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Metadata {
   pub headers: HeadersMap,
   pub url: String,
+  /// Time at which the file was fetched. Entries written by older versions
+  /// don't have it.
+  #[serde(default)]
+  pub fetched_at: Option<SystemTime>,
+  /// Checksum of the content, see `HttpCache::get()`. Entries written by
+  /// older versions don't have it.
+  #[serde(default)]
+  pub content_hash: Option<String>,
 }
 
 impl Metadata {
   pub fn write(&self, cache_filename: &Path) -> Result<(), AnyError> {
     let metadata_filename = Self::filename(cache_filename);
     let json = serde_json::to_string_pretty(self)?;
-    deno_fs::write_file(&metadata_filename, json, 0o666)?;
+    write_file_atomically(&metadata_filename, json.as_bytes())?;
     Ok(())
   }
 
@@ -96,12 +148,96 @@ impl Metadata {
     Ok(metadata)
   }
 
+  /// Returns true if the entry is past the `max-age` of its `cache-control`
+  /// header, or if that header forbids reusing it without revalidation.
+  /// Entries without a `max-age` or a recorded fetch time are never stale.
+  pub fn is_stale(&self, now: SystemTime) -> bool {
+    let cache_control = match self
+      .headers
+      .iter()
+      .find(|(key, _)| key.eq_ignore_ascii_case("cache-control"))
+    {
+      Some((_, value)) => value.to_lowercase(),
+      None => return false,
+    };
+    let mut max_age = None;
+    for directive in cache_control.split(',').map(str::trim) {
+      if directive == "no-cache" || directive == "no-store" {
+        return true;
+      }
+      if let Some(value) = directive.strip_prefix("max-age=") {
+        max_age = value.trim_matches('"').parse::<u64>().ok();
+      }
+    }
+    match (max_age, self.fetched_at) {
+      (Some(max_age), Some(fetched_at)) => match now.duration_since(fetched_at)
+      {
+        Ok(age) => age.as_secs() > max_age,
+        Err(_) => false,
+      },
+      _ => false,
+    }
+  }
+
   /// Ex: $DENO_DIR/deps/https/deno.land/c885b7dcf1d6936e33a9cc3a2d74ec79bab5d733d3701c85a029b7f7ec9fbed4.metadata.json
   pub fn filename(cache_filename: &Path) -> PathBuf {
     cache_filename.with_extension("metadata.json")
   }
 }
 
+/// Writes the content of a cache entry in chunks, see `HttpCache::writer()`.
+pub struct CacheEntryWriter {
+  url: Url,
+  cache_filename: PathBuf,
+  temp_filename: PathBuf,
+  file: Option<tokio::fs::File>,
+  hasher: Option<checksum::Hasher>,
+}
+
+impl CacheEntryWriter {
+  pub async fn write(&mut self, chunk: &[u8]) -> Result<(), AnyError> {
+    self.file.as_mut().unwrap().write_all(chunk).await?;
+    self.hasher.as_mut().unwrap().update(chunk);
+    Ok(())
+  }
+
+  /// Replaces the entry with the written content and `headers_map`, in the
+  /// same order as `HttpCache::set()`.
+  pub async fn finish(
+    mut self,
+    headers_map: HeadersMap,
+  ) -> Result<(), AnyError> {
+    let mut file = self.file.take().unwrap();
+    file.flush().await?;
+    drop(file);
+
+    let metadata = Metadata {
+      url: self.url.to_string(),
+      headers: headers_map,
+      fetched_at: Some(SystemTime::now()),
+      content_hash: Some(self.hasher.take().unwrap().finish()),
+    };
+    let result = metadata.write(&self.cache_filename).and_then(|_| {
+      fs::rename(&self.temp_filename, &self.cache_filename)?;
+      Ok(())
+    });
+    if result.is_err() {
+      let _ = fs::remove_file(&self.temp_filename);
+    }
+    result
+  }
+}
+
+impl Drop for CacheEntryWriter {
+  fn drop(&mut self) {
+    // The content is incomplete if the writer wasn't finished.
+    if let Some(file) = self.file.take() {
+      drop(file);
+      let _ = fs::remove_file(&self.temp_filename);
+    }
+  }
+}
+
 impl HttpCache {
   /// Returns a new instance.
   ///
@@ -110,6 +246,40 @@ impl HttpCache {
     assert!(location.is_absolute());
     Self {
       location: location.to_owned(),
+      query_ignored_hosts: Arc::default(),
+    }
+  }
+
+  /// Ignore the query string of the URLs of `host` when looking up and
+  /// storing entries, so that URLs that only differ by a cache busting query,
+  /// e.g. `?v=<hash>`, share one entry. Only use this for hosts whose
+  /// responses don't vary on the query.
+  pub fn with_query_ignored_for_host(mut self, host: &str) -> Self {
+    Arc::make_mut(&mut self.query_ignored_hosts).insert(host.to_string());
+    self
+  }
+
+  /// Also ignore the query string of the hosts that `other` ignores it for,
+  /// e.g. when replacing `other` with this cache.
+  pub fn with_query_ignored_hosts_of(mut self, other: &HttpCache) -> Self {
+    if !other.query_ignored_hosts.is_empty() {
+      Arc::make_mut(&mut self.query_ignored_hosts)
+        .extend(other.query_ignored_hosts.iter().cloned());
+    }
+    self
+  }
+
+  /// Returns the URL that the entry of `url` is stored under.
+  fn cache_key<'a>(&self, url: &'a Url) -> Cow<'a, Url> {
+    match url.host_str() {
+      Some(host)
+        if url.query().is_some() && self.query_ignored_hosts.contains(host) =>
+      {
+        let mut url = url.clone();
+        url.set_query(None);
+        Cow::Owned(url)
+      }
+      _ => Cow::Borrowed(url),
     }
   }
 
@@ -130,49 +300,170 @@ impl HttpCache {
   }
 
   pub(crate) fn get_cache_filename(&self, url: &Url) -> PathBuf {
-    self.location.join(url_to_filename(url))
+    self.location.join(url_to_filename(&self.cache_key(url)))
   }
 
+  /// Returns the content and headers of the entry for `url`. The content is
+  /// checked against the hash in the metadata, and read again while an
+  /// entry is being replaced, so that it is never paired with the headers of
+  /// another version of the entry. Fails with `InvalidData` if they still
+  /// don't match, e.g. because a write was interrupted.
   // TODO(bartlomieju): this method should check headers file
   // and validate against ETAG/Last-modified-as headers.
   // ETAG check is currently done in `cli/file_fetcher.rs`.
   pub fn get(&self, url: &Url) -> Result<(File, HeadersMap), AnyError> {
-    let cache_filename = self.location.join(url_to_filename(url));
-    let metadata_filename = Metadata::filename(&cache_filename);
-    let file = File::open(cache_filename)?;
-    let metadata = fs::read_to_string(metadata_filename)?;
-    let metadata: Metadata = serde_json::from_str(&metadata)?;
-    Ok((file, metadata.headers))
+    let cache_filename = self.get_cache_filename(url);
+    for attempt in 0..GET_ATTEMPTS {
+      if attempt > 0 {
+        std::thread::sleep(Duration::from_millis(10));
+      }
+      let mut file = File::open(&cache_filename)?;
+      let metadata = Metadata::read(&cache_filename)?;
+      let content_hash = match &metadata.content_hash {
+        Some(content_hash) => content_hash,
+        None => return Ok((file, metadata.headers)),
+      };
+      let mut hasher = checksum::Hasher::default();
+      let mut buf = [0; 64 * 1024];
+      loop {
+        let len = file.read(&mut buf)?;
+        if len == 0 {
+          break;
+        }
+        hasher.update(&buf[..len]);
+      }
+      if hasher.finish() == *content_hash {
+        file.seek(SeekFrom::Start(0))?;
+        return Ok((file, metadata.headers));
+      }
+    }
+    let message =
+      format!("Cache entry for \"{}\" doesn't match its metadata", url);
+    Err(io::Error::new(io::ErrorKind::InvalidData, message).into())
   }
 
   pub fn get_metadata(&self, url: &Url) -> Result<Metadata, AnyError> {
-    let cache_filename = self.location.join(url_to_filename(url));
+    let cache_filename = self.get_cache_filename(url);
     let metadata_filename = Metadata::filename(&cache_filename);
     let metadata = fs::read_to_string(metadata_filename)?;
     let metadata: Metadata = serde_json::from_str(&metadata)?;
     Ok(metadata)
   }
 
+  /// Records that the entry for `url` was just revalidated, so that its age
+  /// starts over, see `Metadata::is_stale()`. The content hash is kept, so
+  /// if the entry is replaced meanwhile, `get()` rejects the mismatch rather
+  /// than pairing the new content with the old headers.
+  pub fn touch(&self, url: &Url) -> Result<(), AnyError> {
+    let cache_filename = self.get_cache_filename(url);
+    let mut metadata = Metadata::read(&cache_filename)?;
+    metadata.fetched_at = Some(SystemTime::now());
+    metadata.write(&cache_filename)
+  }
+
+  /// Returns the cache filename and metadata of every entry in the cache.
+  /// Entries with missing content or unreadable metadata are skipped.
+  pub fn entries(&self) -> Result<Vec<(PathBuf, Metadata)>, AnyError> {
+    let entries = self
+      .entries_with_errors()?
+      .into_iter()
+      .filter_map(|(cache_filename, maybe_metadata)| {
+        maybe_metadata
+          .ok()
+          .map(|metadata| (cache_filename, metadata))
+      })
+      .collect();
+    Ok(entries)
+  }
+
+  /// Like `entries()`, but entries with unreadable metadata are returned
+  /// along with the error.
+  pub fn entries_with_errors(
+    &self,
+  ) -> Result<Vec<(PathBuf, Result<Metadata, AnyError>)>, AnyError> {
+    let mut entries = Vec::new();
+    if !self.location.is_dir() {
+      return Ok(entries);
+    }
+    for entry in WalkDir::new(&self.location) {
+      let entry = match entry {
+        Ok(entry) => entry,
+        Err(err) if err.depth() == 0 => return Err(err.into()),
+        Err(_) => continue,
+      };
+      let path = entry.path();
+      let cache_filename = match path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_suffix(".metadata.json"))
+      {
+        Some(stem) => path.with_file_name(stem),
+        None => continue,
+      };
+      if !cache_filename.is_file() {
+        continue;
+      }
+      let maybe_metadata = Metadata::read(&cache_filename);
+      entries.push((cache_filename, maybe_metadata));
+    }
+    Ok(entries)
+  }
+
+  /// Deletes the content and metadata files of the entry stored at
+  /// `cache_filename`.
+  pub fn remove(&self, cache_filename: &Path) -> Result<(), AnyError> {
+    fs::remove_file(cache_filename)?;
+    let metadata_filename = Metadata::filename(cache_filename);
+    if metadata_filename.exists() {
+      fs::remove_file(metadata_filename)?;
+    }
+    Ok(())
+  }
+
+  /// Returns a writer for the content of `url`, for content that is too large
+  /// to be passed to `set()` at once. The content goes to a temporary file,
+  /// which only replaces the entry once the writer is finished.
+  pub async fn writer(&self, url: &Url) -> Result<CacheEntryWriter, AnyError> {
+    let cache_filename = self.get_cache_filename(url);
+    let parent_filename = cache_filename
+      .parent()
+      .expect("Cache filename should have a parent dir");
+    self.ensure_dir_exists(parent_filename)?;
+    let temp_filename = temp_filename(&cache_filename);
+    let file = tokio::fs::File::create(&temp_filename).await?;
+    Ok(CacheEntryWriter {
+      url: url.clone(),
+      cache_filename,
+      temp_filename,
+      file: Some(file),
+      hasher: Some(checksum::Hasher::default()),
+    })
+  }
+
   pub fn set(
     &self,
     url: &Url,
     headers_map: HeadersMap,
     content: &[u8],
   ) -> Result<(), AnyError> {
-    let cache_filename = self.location.join(url_to_filename(url));
+    let cache_filename = self.get_cache_filename(url);
     // Create parent directory
     let parent_filename = cache_filename
       .parent()
       .expect("Cache filename should have a parent dir");
     self.ensure_dir_exists(parent_filename)?;
-    // Cache content
-    deno_fs::write_file(&cache_filename, content, 0o666)?;
-
+    // Neither file is ever partially written. The metadata, with the hash of
+    // the new content, is replaced first; `get()` waits for the content to
+    // match it.
     let metadata = Metadata {
       url: url.to_string(),
       headers: headers_map,
+      fetched_at: Some(SystemTime::now()),
+      content_hash: Some(checksum::gen(&[content])),
     };
-    metadata.write(&cache_filename)
+    metadata.write(&cache_filename)?;
+    write_file_atomically(&cache_filename, content)?;
+    Ok(())
   }
 }
 
@@ -239,6 +530,66 @@ mod tests {
     assert_eq!(headers.get("foobar"), None);
   }
 
+  #[tokio::test]
+  async fn test_writer() {
+    let dir = TempDir::new().unwrap();
+    let cache = HttpCache::new(dir.path());
+    let url = Url::parse("https://deno.land/x/data.bin").unwrap();
+    let cache_filename = cache.get_cache_filename(&url);
+
+    let mut writer = cache.writer(&url).await.unwrap();
+    writer.write(b"Hello ").await.unwrap();
+    writer.write(b"world").await.unwrap();
+    assert!(!cache_filename.exists());
+    let mut headers = HashMap::new();
+    headers.insert("etag".to_string(), "as5625rqdsfb".to_string());
+    writer.finish(headers).await.unwrap();
+
+    let (mut file, headers) = cache.get(&url).unwrap();
+    let mut content = String::new();
+    file.read_to_string(&mut content).unwrap();
+    assert_eq!(content, "Hello world");
+    assert_eq!(headers.get("etag").unwrap(), "as5625rqdsfb");
+
+    // An unfinished writer leaves the entry as it was.
+    let mut writer = cache.writer(&url).await.unwrap();
+    writer.write(b"Goodbye").await.unwrap();
+    drop(writer);
+    assert_eq!(fs::read_to_string(&cache_filename).unwrap(), "Hello world");
+    assert_eq!(
+      fs::read_dir(cache_filename.parent().unwrap())
+        .unwrap()
+        .count(),
+      2
+    );
+  }
+
+  #[test]
+  fn test_metadata_is_stale() {
+    let now = SystemTime::now();
+    let an_hour_ago = now - std::time::Duration::from_secs(3600);
+    let metadata = |cache_control: Option<&str>, fetched_at| {
+      let mut headers = HashMap::new();
+      if let Some(cache_control) = cache_control {
+        headers.insert("cache-control".to_string(), cache_control.to_string());
+      }
+      Metadata {
+        headers,
+        url: "https://deno.land/x/welcome.ts".to_string(),
+        fetched_at,
+        content_hash: None,
+      }
+    };
+
+    assert!(!metadata(None, Some(an_hour_ago)).is_stale(now));
+    assert!(!metadata(Some("max-age=7200"), Some(an_hour_ago)).is_stale(now));
+    assert!(
+      metadata(Some("public, max-age=60"), Some(an_hour_ago)).is_stale(now)
+    );
+    assert!(!metadata(Some("max-age=60"), None).is_stale(now));
+    assert!(metadata(Some("no-cache"), Some(now)).is_stale(now));
+  }
+
   #[test]
   fn test_url_to_filename() {
     let test_cases = [
@@ -266,4 +617,134 @@ mod tests {
       assert_eq!(p, PathBuf::from(expected));
     }
   }
+
+  #[test]
+  fn test_query_ignored_for_host() {
+    let dir = TempDir::new().unwrap();
+    let cache =
+      HttpCache::new(dir.path()).with_query_ignored_for_host("cdn.test");
+    let url = Url::parse("https://cdn.test/mod.ts?v=1").unwrap();
+    cache.set(&url, HashMap::new(), b"export {};").unwrap();
+
+    let other_query = Url::parse("https://cdn.test/mod.ts?v=2").unwrap();
+    assert_eq!(
+      cache.get_cache_filename(&other_query),
+      cache.get_cache_filename(&url)
+    );
+    assert!(cache.get(&other_query).is_ok());
+    let other_host = Url::parse("https://other.test/mod.ts?v=1").unwrap();
+    let other_host_v2 = Url::parse("https://other.test/mod.ts?v=2").unwrap();
+    assert_ne!(
+      cache.get_cache_filename(&other_host),
+      cache.get_cache_filename(&other_host_v2)
+    );
+  }
+
+  #[test]
+  fn test_set_is_atomic() {
+    let dir = TempDir::new().unwrap();
+    let cache = HttpCache::new(dir.path());
+    let url = Url::parse("https://deno.land/x/large.js").unwrap();
+    let contents = [vec![b'a'; 1 << 20], vec![b'b'; 1 << 20]];
+    cache.set(&url, HashMap::new(), &contents[0]).unwrap();
+
+    let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let writer = {
+      let cache = cache.clone();
+      let url = url.clone();
+      let contents = contents.clone();
+      let done = done.clone();
+      std::thread::spawn(move || {
+        for i in 0..20 {
+          cache.set(&url, HashMap::new(), &contents[i % 2]).unwrap();
+        }
+        done.store(true, Ordering::SeqCst);
+      })
+    };
+    // Readers never see a partially written file.
+    while !done.load(Ordering::SeqCst) {
+      let (mut file, _) = cache.get(&url).unwrap();
+      let mut content = Vec::new();
+      file.read_to_end(&mut content).unwrap();
+      assert!(content == contents[0] || content == contents[1]);
+    }
+    writer.join().unwrap();
+
+    // No temporary files are left behind.
+    let cache_filename = cache.get_cache_filename(&url);
+    let files = fs::read_dir(cache_filename.parent().unwrap()).unwrap();
+    assert_eq!(files.count(), 2);
+  }
+
+  #[test]
+  fn test_set_keeps_headers_and_content_together() {
+    let dir = TempDir::new().unwrap();
+    let cache = HttpCache::new(dir.path());
+    let url = Url::parse("https://deno.land/x/large.js").unwrap();
+    let versions = [("a", vec![b'a'; 1 << 20]), ("b", vec![b'b'; 1 << 20])];
+    let headers = |etag: &str| {
+      let mut headers = HashMap::new();
+      headers.insert("etag".to_string(), etag.to_string());
+      headers
+    };
+    cache.set(&url, headers("a"), &versions[0].1).unwrap();
+
+    let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let writer = {
+      let cache = cache.clone();
+      let url = url.clone();
+      let versions = versions.clone();
+      let done = done.clone();
+      std::thread::spawn(move || {
+        for i in 0..20 {
+          let (etag, content) = &versions[i % 2];
+          cache.set(&url, headers(etag), content).unwrap();
+        }
+        done.store(true, Ordering::SeqCst);
+      })
+    };
+    // Readers never see the headers of one version with the content of the
+    // other.
+    while !done.load(Ordering::SeqCst) {
+      let (mut file, headers) = cache.get(&url).unwrap();
+      let mut content = Vec::new();
+      file.read_to_end(&mut content).unwrap();
+      let (_, expected) = versions
+        .iter()
+        .find(|(etag, _)| headers.get("etag").unwrap() == etag)
+        .unwrap();
+      assert!(content == *expected);
+    }
+    writer.join().unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_get_rejects_mismatched_content() {
+    let dir = TempDir::new().unwrap();
+    let cache = HttpCache::new(dir.path());
+    let url = Url::parse("https://deno.land/x/welcome.ts").unwrap();
+    let mut writer = cache.writer(&url).await.unwrap();
+    writer.write(b"Hello world").await.unwrap();
+    writer.finish(HashMap::new()).await.unwrap();
+    let metadata = cache.get_metadata(&url).unwrap();
+    assert_eq!(
+      metadata.content_hash.unwrap(),
+      checksum::gen(&[b"Hello world"])
+    );
+
+    // E.g. the content of an interrupted write.
+    let cache_filename = cache.get_cache_filename(&url);
+    fs::write(&cache_filename, "Goodbye").unwrap();
+    let err = cache.get(&url).unwrap_err();
+    assert_eq!(
+      err.downcast_ref::<io::Error>().unwrap().kind(),
+      io::ErrorKind::InvalidData
+    );
+
+    // Entries written by older versions have no hash to check.
+    let mut metadata = cache.get_metadata(&url).unwrap();
+    metadata.content_hash = None;
+    metadata.write(&cache_filename).unwrap();
+    assert!(cache.get(&url).is_ok());
+  }
 }
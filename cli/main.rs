@@ -7,6 +7,7 @@ extern crate lazy_static;
 #[macro_use]
 extern crate log;
 
+mod archive;
 mod ast;
 mod checksum;
 pub mod colors;
@@ -262,6 +263,8 @@ async fn eval_command(
     filename: main_module_url.to_file_path().unwrap(),
     url: main_module_url,
     types_header: None,
+    maybe_headers: None,
+    maybe_bytes: None,
     media_type: if as_typescript {
       MediaType::TypeScript
     } else {
@@ -450,6 +453,8 @@ async fn run_from_stdin(flags: Flags) -> Result<(), AnyError> {
     filename: main_module_url.to_file_path().unwrap(),
     url: main_module_url,
     types_header: None,
+    maybe_headers: None,
+    maybe_bytes: None,
     media_type: MediaType::TypeScript,
     source_code: source.into(),
   };
@@ -577,6 +582,8 @@ async fn test_command(
     filename: test_file_url.to_file_path().unwrap(),
     url: test_file_url.clone(),
     types_header: None,
+    maybe_headers: None,
+    maybe_bytes: None,
     media_type: MediaType::TypeScript,
     source_code: TextDocument::new(
       test_file.clone().into_bytes(),
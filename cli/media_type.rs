@@ -51,8 +51,15 @@ impl MediaType {
         Some("jsx") => MediaType::JSX,
         Some("mjs") => MediaType::JavaScript,
         Some("cjs") => MediaType::JavaScript,
-        Some("json") => MediaType::Json,
-        Some("wasm") => MediaType::Wasm,
+        Some("mts") | Some("cts") => {
+          if is_dts_stem(path) {
+            MediaType::Dts
+          } else {
+            MediaType::TypeScript
+          }
+        }
+        Some("json") | Some("jsonc") => MediaType::Json,
+        Some("wasm") | Some("wat") => MediaType::Wasm,
         _ => MediaType::Unknown,
       },
     }
@@ -83,6 +90,14 @@ impl MediaType {
   }
 }
 
+/// Returns true if the file stem of `path` ends in `.d`, as in `mod.d.mts`.
+pub fn is_dts_stem(path: &Path) -> bool {
+  path
+    .file_stem()
+    .and_then(|stem| stem.to_str())
+    .map_or(false, |stem| stem.ends_with(".d"))
+}
+
 impl Serialize for MediaType {
   fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
   where
@@ -134,11 +149,23 @@ fn test_map_file_extension() {
   );
   assert_eq!(MediaType::from(Path::new("foo/bar.jsx")), MediaType::JSX);
   assert_eq!(MediaType::from(Path::new("foo/bar.json")), MediaType::Json);
+  assert_eq!(MediaType::from(Path::new("foo/bar.jsonc")), MediaType::Json);
   assert_eq!(MediaType::from(Path::new("foo/bar.wasm")), MediaType::Wasm);
+  assert_eq!(MediaType::from(Path::new("foo/bar.wat")), MediaType::Wasm);
   assert_eq!(
     MediaType::from(Path::new("foo/bar.cjs")),
     MediaType::JavaScript
   );
+  assert_eq!(
+    MediaType::from(Path::new("foo/bar.mts")),
+    MediaType::TypeScript
+  );
+  assert_eq!(
+    MediaType::from(Path::new("foo/bar.cts")),
+    MediaType::TypeScript
+  );
+  assert_eq!(MediaType::from(Path::new("foo/bar.d.mts")), MediaType::Dts);
+  assert_eq!(MediaType::from(Path::new("foo/bar.d.cts")), MediaType::Dts);
   assert_eq!(
     MediaType::from(Path::new("foo/bar.txt")),
     MediaType::Unknown
@@ -428,6 +428,13 @@ impl ModuleGraphLoader {
     let file_fetcher = self.file_fetcher.clone();
     let perms = self.permissions.clone();
 
+    // The location of the import names the referrer, so the error of the
+    // fetch doesn't name it again.
+    let maybe_referrer = if maybe_location.is_some() {
+      None
+    } else {
+      maybe_referrer
+    };
     let load_future = async move {
       let spec_ = spec.clone();
       let source_file = file_fetcher
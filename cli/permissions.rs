@@ -8,6 +8,7 @@ use deno_core::error::uri_error;
 use deno_core::error::AnyError;
 use deno_core::url;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::env::current_dir;
 use std::fmt;
@@ -19,8 +20,10 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::AtomicBool;
 #[cfg(test)]
 use std::sync::atomic::Ordering;
-#[cfg(test)]
+use std::sync::Arc;
+use std::sync::Condvar;
 use std::sync::Mutex;
+use std::sync::MutexGuard;
 
 const PERMISSION_EMOJI: &str = "⚠️";
 
@@ -112,6 +115,81 @@ impl<T: Eq + Hash> UnaryPermission<T> {
   }
 }
 
+/// Answers to network access prompts, keyed by `host:port`. Clones of a
+/// `Permissions` share them, forks start without any. A `None` answer marks
+/// a prompt that is still being answered.
+#[derive(Clone, Debug, Default)]
+pub struct NetPromptAnswers(
+  Arc<(Mutex<HashMap<String, Option<bool>>>, Condvar)>,
+);
+
+impl NetPromptAnswers {
+  fn lock(&self) -> MutexGuard<HashMap<String, Option<bool>>> {
+    // The map is never left half updated, so a panic elsewhere while it was
+    // locked doesn't matter.
+    (self.0).0.lock().unwrap_or_else(|err| err.into_inner())
+  }
+
+  /// Returns the answer for `key`, asking `prompt` if there is none yet. The
+  /// lock isn't held while prompting; concurrent calls for the same key wait
+  /// for the pending answer rather than asking again.
+  fn get_or_prompt(&self, key: String, prompt: impl FnOnce() -> bool) -> bool {
+    let mut answers = self.lock();
+    loop {
+      match answers.get(&key) {
+        Some(Some(answer)) => return *answer,
+        Some(None) => {
+          answers = (self.0)
+            .1
+            .wait(answers)
+            .unwrap_or_else(|err| err.into_inner());
+        }
+        None => break,
+      }
+    }
+    answers.insert(key.clone(), None);
+    drop(answers);
+
+    let mut pending = PendingNetPrompt {
+      answers: self,
+      key,
+      answer: None,
+    };
+    let answer = prompt();
+    pending.answer = Some(answer);
+    answer
+  }
+}
+
+impl PartialEq for NetPromptAnswers {
+  fn eq(&self, other: &Self) -> bool {
+    Arc::ptr_eq(&self.0, &other.0) || *self.lock() == *other.lock()
+  }
+}
+
+/// Stores the answer of a prompt once it is known, or forgets the pending
+/// prompt if it panicked, and wakes up the calls waiting for it.
+struct PendingNetPrompt<'a> {
+  answers: &'a NetPromptAnswers,
+  key: String,
+  answer: Option<bool>,
+}
+
+impl Drop for PendingNetPrompt<'_> {
+  fn drop(&mut self) {
+    let mut answers = self.answers.lock();
+    match self.answer {
+      Some(answer) => {
+        answers.insert(self.key.clone(), Some(answer));
+      }
+      None => {
+        answers.remove(&self.key);
+      }
+    }
+    (self.answers.0).1.notify_all();
+  }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
 pub struct Permissions {
   pub read: UnaryPermission<PathBuf>,
@@ -121,6 +199,8 @@ pub struct Permissions {
   pub run: PermissionState,
   pub plugin: PermissionState,
   pub hrtime: PermissionState,
+  #[serde(skip)]
+  pub net_prompt_answers: NetPromptAnswers,
 }
 
 fn resolve_fs_allowlist(allowlist: &[PathBuf]) -> HashSet<PathBuf> {
@@ -152,6 +232,7 @@ impl Permissions {
       run: PermissionState::from(flags.allow_run),
       plugin: PermissionState::from(flags.allow_plugin),
       hrtime: PermissionState::from(flags.allow_hrtime),
+      net_prompt_answers: Default::default(),
     }
   }
 
@@ -191,6 +272,7 @@ impl Permissions {
       run: PermissionState::Granted,
       plugin: PermissionState::Granted,
       hrtime: PermissionState::Granted,
+      net_prompt_answers: Default::default(),
     }
   }
 
@@ -572,6 +654,31 @@ impl Permissions {
       .check(&format!("network access to \"{}\"", url), "--allow-net")
   }
 
+  /// As `check_net_url()`, but when access to the host is up to a prompt,
+  /// `prompt` is asked instead of failing. The answer is remembered for the
+  /// host, so it is asked at most once.
+  pub fn check_net_url_or_prompt(
+    &self,
+    url: &url::Url,
+    prompt: impl FnOnce() -> bool,
+  ) -> Result<(), AnyError> {
+    let err = match self.check_net_url(url) {
+      Ok(()) => return Ok(()),
+      Err(err) => err,
+    };
+    let host = url.host_str().unwrap_or_default();
+    let port = url.port_or_known_default();
+    if self.query_net(host, port) != PermissionState::Prompt {
+      return Err(err);
+    }
+    let key = format!("{}:{}", host, port.unwrap_or_default());
+    if self.net_prompt_answers.get_or_prompt(key, prompt) {
+      Ok(())
+    } else {
+      Err(err)
+    }
+  }
+
   pub fn check_env(&self) -> Result<(), AnyError> {
     self
       .env
@@ -622,6 +729,7 @@ impl Permissions {
       run,
       plugin,
       hrtime,
+      net_prompt_answers: Default::default(),
     })
   }
 }
@@ -929,6 +1037,7 @@ mod tests {
       run: PermissionState::Granted,
       hrtime: PermissionState::Granted,
       plugin: PermissionState::Granted,
+      net_prompt_answers: Default::default(),
     };
     let deserialized_perms: Permissions =
       serde_json::from_str(json_perms).unwrap();
@@ -999,6 +1108,7 @@ mod tests {
       run: PermissionState::Granted,
       plugin: PermissionState::Granted,
       hrtime: PermissionState::Granted,
+      net_prompt_answers: Default::default(),
     };
     let perms2 = Permissions {
       read: UnaryPermission {
@@ -1020,6 +1130,7 @@ mod tests {
       run: PermissionState::Prompt,
       plugin: PermissionState::Prompt,
       hrtime: PermissionState::Prompt,
+      net_prompt_answers: Default::default(),
     };
     #[rustfmt::skip]
     {
@@ -1067,6 +1178,7 @@ mod tests {
       run: PermissionState::Prompt,
       plugin: PermissionState::Prompt,
       hrtime: PermissionState::Prompt,
+      net_prompt_answers: Default::default(),
     };
     #[rustfmt::skip]
     {
@@ -1125,6 +1237,7 @@ mod tests {
       run: PermissionState::Granted,
       plugin: PermissionState::Prompt,
       hrtime: PermissionState::Denied,
+      net_prompt_answers: Default::default(),
     };
     #[rustfmt::skip]
     {
@@ -1141,4 +1254,43 @@ mod tests {
       assert_eq!(perms.revoke_hrtime(), PermissionState::Denied);
     };
   }
+
+  #[test]
+  fn test_check_net_url_or_prompt_asks_once() {
+    let perms = Permissions::default();
+    let url = url::Url::parse("http://localhost:4545/mod.ts").unwrap();
+    let prompts = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let handles = (0..4)
+      .map(|_| {
+        let perms = perms.clone();
+        let url = url.clone();
+        let prompts = prompts.clone();
+        std::thread::spawn(move || {
+          perms.check_net_url_or_prompt(&url, || {
+            prompts.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            true
+          })
+        })
+      })
+      .collect::<Vec<_>>();
+    for handle in handles {
+      assert!(handle.join().unwrap().is_ok());
+    }
+    // Concurrent checks waited for the first answer.
+    assert_eq!(prompts.load(Ordering::SeqCst), 1);
+  }
+
+  #[test]
+  fn test_check_net_url_or_prompt_panic() {
+    let perms = Permissions::default();
+    let url = url::Url::parse("http://localhost:4545/mod.ts").unwrap();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+      perms.check_net_url_or_prompt(&url, || panic!("prompt failed"))
+    }));
+    assert!(result.is_err());
+    // The failed prompt isn't remembered, so it is asked again.
+    assert!(perms.check_net_url_or_prompt(&url, || true).is_ok());
+    assert!(perms.check_net_url_or_prompt(&url, || false).is_ok());
+  }
 }
@@ -1,5 +1,6 @@
 // Copyright 2018-2020 the Deno authors. All rights reserved. MIT license.
 
+use crate::colors;
 use crate::deno_dir::DenoDir;
 use crate::disk_cache::DiskCache;
 use crate::file_fetcher::SourceFileFetcher;
@@ -239,6 +240,9 @@ impl SpecifierHandler for FetchHandler {
       let source_file = file_fetcher
         .fetch_source_file(&specifier, None, permissions)
         .await?;
+      for (_, warning) in file_fetcher.take_warnings() {
+        eprintln!("{} {}", colors::yellow("Warning"), warning);
+      }
       let url = source_file.url;
       let filename = disk_cache.get_cache_filename_with_extension(&url, "meta");
       let maybe_version = if let Ok(bytes) = disk_cache.get(&filename) {
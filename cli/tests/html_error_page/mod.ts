@@ -0,0 +1,5 @@
+<!DOCTYPE html>
+<html>
+  <head><title>Page not found</title></head>
+  <body><h1>Page not found</h1></body>
+</html>
@@ -0,0 +1,4 @@
+<html>
+  <head><title>Page not found</title></head>
+  <body><h1>Page not found</h1></body>
+</html>
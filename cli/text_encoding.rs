@@ -3,17 +3,27 @@
 use encoding_rs::*;
 use std::{
   borrow::Cow,
+  convert::TryInto,
   io::{Error, ErrorKind},
 };
 
+const UTF32_LE_BOM: &[u8] = b"\xFF\xFE\x00\x00";
+const UTF32_BE_BOM: &[u8] = b"\x00\x00\xFE\xFF";
+
 /// Attempts to detect the character encoding of the provided bytes.
 ///
-/// Supports UTF-8, UTF-16 Little Endian and UTF-16 Big Endian.
+/// Supports UTF-8, UTF-16 Little Endian, UTF-16 Big Endian, UTF-32 Little
+/// Endian and UTF-32 Big Endian.
 pub fn detect_charset(bytes: &'_ [u8]) -> &'static str {
   const UTF16_LE_BOM: &[u8] = b"\xFF\xFE";
   const UTF16_BE_BOM: &[u8] = b"\xFE\xFF";
 
-  if bytes.starts_with(UTF16_LE_BOM) {
+  // The UTF-32LE BOM starts with the UTF-16LE one, so it is checked first.
+  if bytes.starts_with(UTF32_LE_BOM) {
+    "utf-32le"
+  } else if bytes.starts_with(UTF32_BE_BOM) {
+    "utf-32be"
+  } else if bytes.starts_with(UTF16_LE_BOM) {
     "utf-16le"
   } else if bytes.starts_with(UTF16_BE_BOM) {
     "utf-16be"
@@ -23,15 +33,40 @@ pub fn detect_charset(bytes: &'_ [u8]) -> &'static str {
   }
 }
 
+/// Returns true if the provided bytes start with a UTF-8, UTF-16 or UTF-32
+/// byte order mark, in which case `detect_charset` is conclusive.
+pub fn has_bom(bytes: &'_ [u8]) -> bool {
+  const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+  const UTF16_LE_BOM: &[u8] = b"\xFF\xFE";
+  const UTF16_BE_BOM: &[u8] = b"\xFE\xFF";
+
+  bytes.starts_with(UTF8_BOM)
+    || bytes.starts_with(UTF16_LE_BOM)
+    || bytes.starts_with(UTF16_BE_BOM)
+    || bytes.starts_with(UTF32_BE_BOM)
+}
+
 /// Attempts to convert the provided bytes to a UTF-8 string.
 ///
 /// Supports all encodings supported by the encoding_rs crate, which includes
-/// all encodings specified in the WHATWG Encoding Standard, and only those
-/// encodings (see: https://encoding.spec.whatwg.org/).
+/// all encodings specified in the WHATWG Encoding Standard (see:
+/// https://encoding.spec.whatwg.org/), as well as UTF-32, which the standard
+/// leaves out. As for UTF-16, a byte order mark is kept in the output.
 pub fn convert_to_utf8<'a>(
   bytes: &'a [u8],
   charset: &'_ str,
 ) -> Result<Cow<'a, str>, Error> {
+  match charset.trim().to_ascii_lowercase().as_str() {
+    "utf-32le" => return decode_utf32(bytes, false).map(Cow::Owned),
+    "utf-32be" => return decode_utf32(bytes, true).map(Cow::Owned),
+    // Without an explicit byte order, UTF-32 is big endian unless its byte
+    // order mark says otherwise.
+    "utf-32" => {
+      let big_endian = !bytes.starts_with(UTF32_LE_BOM);
+      return decode_utf32(bytes, big_endian).map(Cow::Owned);
+    }
+    _ => {}
+  }
   match Encoding::for_label(charset.as_bytes()) {
     Some(encoding) => encoding
       .decode_without_bom_handling_and_without_replacement(bytes)
@@ -43,6 +78,25 @@ pub fn convert_to_utf8<'a>(
   }
 }
 
+fn decode_utf32(bytes: &[u8], big_endian: bool) -> Result<String, Error> {
+  if bytes.len() % 4 != 0 {
+    return Err(ErrorKind::InvalidData.into());
+  }
+  bytes
+    .chunks_exact(4)
+    .map(|chunk| {
+      let chunk = chunk.try_into().unwrap();
+      let code_point = if big_endian {
+        u32::from_be_bytes(chunk)
+      } else {
+        u32::from_le_bytes(chunk)
+      };
+      std::char::from_u32(code_point)
+        .ok_or_else(|| Error::from(ErrorKind::InvalidData))
+    })
+    .collect()
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -75,15 +129,59 @@ mod tests {
     test_detection(&test_data, "utf-16be");
   }
 
+  #[test]
+  fn test_detection_utf32_little_endian() {
+    let test_data = b"\xFF\xFE\x00\x00H\x00\x00\x00".to_vec();
+    test_detection(&test_data, "utf-32le");
+  }
+
+  #[test]
+  fn test_detection_utf32_big_endian() {
+    let test_data = b"\x00\x00\xFE\xFF\x00\x00\x00H".to_vec();
+    test_detection(&test_data, "utf-32be");
+  }
+
+  #[test]
+  fn test_has_bom() {
+    assert!(has_bom(b"\xEF\xBB\xBFHello UTF-8"));
+    assert!(has_bom(b"\xFF\xFEHello UTF-16LE"));
+    assert!(has_bom(b"\xFE\xFFHello UTF-16BE"));
+    assert!(has_bom(b"\x00\x00\xFE\xFF\x00\x00\x00H"));
+    assert!(!has_bom(b"Hello UTF-8"));
+    assert!(!has_bom(b""));
+  }
+
   #[test]
   fn test_decoding_unsupported_charset() {
     let test_data = Vec::new();
-    let result = convert_to_utf8(&test_data, "utf-32le");
+    let result = convert_to_utf8(&test_data, "utf-7");
     assert!(result.is_err());
     let err = result.expect_err("Err expected");
     assert!(err.kind() == ErrorKind::InvalidInput);
   }
 
+  #[test]
+  fn test_decoding_utf32() {
+    let le = b"\xFF\xFE\x00\x00H\x00\x00\x00\xF0\x23\x00\x00";
+    let be = b"\x00\x00\xFE\xFF\x00\x00\x00H\x00\x00\x23\xF0";
+    assert_eq!(
+      convert_to_utf8(le, "utf-32le").unwrap(),
+      "\u{FEFF}H\u{23F0}"
+    );
+    assert_eq!(
+      convert_to_utf8(be, "UTF-32BE").unwrap(),
+      "\u{FEFF}H\u{23F0}"
+    );
+    assert_eq!(convert_to_utf8(le, "utf-32").unwrap(), "\u{FEFF}H\u{23F0}");
+    assert_eq!(convert_to_utf8(be, "utf-32").unwrap(), "\u{FEFF}H\u{23F0}");
+
+    let err = convert_to_utf8(&le[..5], "utf-32le").unwrap_err();
+    assert!(err.kind() == ErrorKind::InvalidData);
+    // Surrogates are not valid code points.
+    let err = convert_to_utf8(b"\x00\xD8\x00\x00", "utf-32le").unwrap_err();
+    assert!(err.kind() == ErrorKind::InvalidData);
+  }
+
   #[test]
   fn test_decoding_invalid_utf8() {
     let test_data = b"\xFE\xFE\xFF\xFF".to_vec();
@@ -868,6 +868,8 @@ impl TsCompiler {
       media_type: MediaType::JavaScript,
       source_code: compiled_code.into(),
       types_header: None,
+      maybe_headers: None,
+      maybe_bytes: None,
     };
 
     Ok(compiled_module)
@@ -925,6 +927,8 @@ impl TsCompiler {
       media_type: MediaType::JavaScript,
       source_code: source_code.into(),
       types_header: None,
+      maybe_headers: None,
+      maybe_bytes: None,
     };
 
     Ok(source_map_file)
@@ -1588,6 +1592,8 @@ mod tests {
       media_type: MediaType::TypeScript,
       source_code: include_bytes!("./tests/002_hello.ts").to_vec().into(),
       types_header: None,
+      maybe_headers: None,
+      maybe_bytes: None,
     };
     let dir =
       deno_dir::DenoDir::new(Some(test_util::new_deno_dir().path().to_owned()))
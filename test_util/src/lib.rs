@@ -285,6 +285,18 @@ pub async fn run_all_servers() {
       );
       Box::new(res)
     });
+  let redirect_with_status = warp::path("redirect_with_status")
+    .and(warp::path::param::<u16>())
+    .and(warp::path::tail())
+    .map(|status: u16, tail: warp::path::Tail| -> Box<dyn Reply> {
+      let mut res = Response::new(Body::from(""));
+      *res.status_mut() = StatusCode::from_u16(status).unwrap();
+      let location = format!("/{}", tail.as_str());
+      res
+        .headers_mut()
+        .insert("Location", HeaderValue::from_str(&location).unwrap());
+      Box::new(res)
+    });
   let bad_redirect = warp::path("bad_redirect").map(|| -> Box<dyn Reply> {
     let mut res = Response::new(Body::from(""));
     *res.status_mut() = StatusCode::FOUND;
@@ -311,6 +323,74 @@ pub async fn run_all_servers() {
         Box::new(res)
       }
     });
+  let user_agent_script = warp::path!("user_agent.ts")
+    .and(warp::header::optional::<String>("user-agent"))
+    .map(|user_agent: Option<String>| {
+      let body = format!(
+        "export const userAgent = {:?};",
+        user_agent.unwrap_or_default()
+      );
+      let mut res = Response::new(Body::from(body));
+      let h = res.headers_mut();
+      h.insert(
+        "Content-type",
+        HeaderValue::from_static("application/typescript"),
+      );
+      h.insert("Vary", HeaderValue::from_static("User-Agent"));
+      res
+    });
+  let negotiated_module = warp::path!("negotiated_module")
+    .and(warp::header::optional::<String>("accept"))
+    .map(|accept: Option<String>| {
+      let is_ts = accept
+        .map(|accept| accept.contains("application/typescript"))
+        .unwrap_or(false);
+      let (body, content_type) = if is_ts {
+        (
+          "export const negotiated: string = \"ts\";",
+          "application/typescript",
+        )
+      } else {
+        (
+          "export const negotiated = \"js\";",
+          "application/javascript",
+        )
+      };
+      let mut res = Response::new(Body::from(body));
+      let h = res.headers_mut();
+      h.insert("Content-type", HeaderValue::from_static(content_type));
+      h.insert("Vary", HeaderValue::from_static("Accept"));
+      res
+    });
+  let slow_body = warp::path!("slow_body.ts").map(|| {
+    // The first chunk is sent right away, then one every 500ms.
+    let chunks = futures::stream::unfold(0, |i| async move {
+      if i == 10 {
+        return None;
+      }
+      if i > 0 {
+        tokio::time::delay_for(std::time::Duration::from_millis(500)).await;
+      }
+      let chunk = format!("// chunk {}\n", i);
+      Some((Ok::<_, std::io::Error>(chunk), i + 1))
+    });
+    let mut res = Response::new(Body::wrap_stream(chunks));
+    res.headers_mut().insert(
+      "Content-type",
+      HeaderValue::from_static("application/typescript"),
+    );
+    res
+  });
+  let large_file = warp::path!("large_file.bin").map(|| {
+    // 10 MiB of the bytes 0 to 255, repeated.
+    let body: Vec<u8> = (0..10 * 1024 * 1024).map(|i| i as u8).collect();
+    let mut res = Response::new(Body::from(body));
+    res.headers_mut().insert(
+      "Content-type",
+      HeaderValue::from_static("application/octet-stream"),
+    );
+    res
+  });
   let xtypescripttypes = warp::path!("xTypeScriptTypes.js")
     .map(|| {
       let mut res = Response::new(Body::from("export const foo = 'foo';"));
@@ -427,10 +507,15 @@ pub async fn run_all_servers() {
     .and(warp::fs::dir(root_path()))
     .map(custom_headers)
     .or(etag_script)
+    .or(user_agent_script)
+    .or(negotiated_module)
+    .or(large_file)
+    .or(slow_body)
     .or(xtypescripttypes)
     .or(echo_server)
     .or(echo_multipart_file)
     .or(multipart_form_data)
+    .or(redirect_with_status)
     .or(bad_redirect);
 
   let http_fut =
@@ -476,6 +561,11 @@ fn custom_headers(path: warp::path::Peek, f: warp::fs::File) -> Box<dyn Reply> {
     let f = with_header(f, "X-Deno-Warning", "foobar");
     return Box::new(f);
   }
+  if p.ends_with("cli/tests/subdir/content_location/alias.ts") {
+    let f = with_header(f, "Content-Type", "application/typescript");
+    let f = with_header(f, "Content-Location", "canonical.ts");
+    return Box::new(f);
+  }
   if p.ends_with("cli/tests/053_import_compression/brotli") {
     let f = with_header(f, "Content-Encoding", "br");
     let f = with_header(f, "Content-Type", "application/javascript");
@@ -502,6 +592,10 @@ fn custom_headers(path: warp::path::Peek, f: warp::fs::File) -> Box<dyn Reply> {
     return Box::new(f);
   }
 
+  if p.ends_with("cli/tests/html_error_page/mod.ts") {
+    return Box::new(with_header(f, "Content-Type", "text/html"));
+  }
+
   let content_type = if p.contains(".t1.") {
     Some("text/typescript")
   } else if p.contains(".t2.") {
@@ -518,6 +612,8 @@ fn custom_headers(path: warp::path::Peek, f: warp::fs::File) -> Box<dyn Reply> {
     Some("text/ecmascript")
   } else if p.contains(".j4.") {
     Some("application/x-javascript")
+  } else if p.contains("text_plain") {
+    Some("text/plain")
   } else if p.contains("form_urlencoded") {
     Some("application/x-www-form-urlencoded")
   } else if p.contains("unknown_ext") || p.contains("no_ext") {
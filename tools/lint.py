@@ -71,6 +71,7 @@ def eslint():
         ":!:cli/dts/**",
         ":!:cli/tests/encoding/**",
         ":!:cli/tests/error_syntax.js",
+        ":!:cli/tests/html_error_page/**",
         ":!:cli/tests/lint/**",
         ":!:cli/tests/tsc/**",
         ":!:cli/tsc/*typescript.js",